@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::command;
+
+/// How the caller wants the response body decoded.
+#[derive(Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ResponseType {
+    Json,
+    Text,
+    Binary,
+}
+
+#[derive(Deserialize)]
+pub struct HttpRequestOptions {
+    method: String,
+    url: String,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    body: Option<Vec<u8>>,
+    #[serde(default)]
+    connect_timeout: Option<u64>,
+    #[serde(default)]
+    read_timeout: Option<u64>,
+    #[serde(default = "default_follow_redirects")]
+    follow_redirects: bool,
+    #[serde(default = "default_max_redirections")]
+    max_redirections: usize,
+    response_type: ResponseType,
+}
+
+fn default_follow_redirects() -> bool {
+    true
+}
+
+fn default_max_redirections() -> usize {
+    10
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase", tag = "type", content = "data")]
+enum ResponseBody {
+    Json(serde_json::Value),
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+#[derive(Serialize)]
+pub struct HttpResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: ResponseBody,
+}
+
+/// Hosts the frontend is allowed to reach through this command, mirroring the
+/// URL scope a real Tauri `httpRequest` capability would configure. Without
+/// this, any webview content (including a compromised or third-party iframe)
+/// could use the command as an open proxy to reach internal/loopback
+/// services, e.g. a cloud metadata endpoint.
+const ALLOWED_HOSTS: &[&str] = &["api.workos.com", "api.hazel.chat"];
+
+/// Check `url`'s host against [`ALLOWED_HOSTS`], matching the exact host or
+/// any subdomain of it. IP-literal hosts (including loopback) are never
+/// allowed, since they're exactly the SSRF targets the scope exists to block.
+fn is_in_scope(url: &url::Url) -> bool {
+    let Some(domain) = url.domain() else {
+        return false;
+    };
+    ALLOWED_HOSTS
+        .iter()
+        .any(|allowed| domain == *allowed || domain.ends_with(&format!(".{}", allowed)))
+}
+
+/// Make an HTTP request from Rust rather than the webview, so flows like the
+/// OAuth code-for-token exchange can keep the token out of JS entirely.
+/// Mirrors the classic Tauri `httpRequest` API surface, including being
+/// gated by a URL scope.
+#[command]
+pub async fn http_request(options: HttpRequestOptions) -> Result<HttpResponse, String> {
+    let parsed_url =
+        url::Url::parse(&options.url).map_err(|e| format!("Invalid URL: {}", e))?;
+    if !is_in_scope(&parsed_url) {
+        return Err(format!("URL not allowed by scope: {}", options.url));
+    }
+
+    let method = reqwest::Method::from_bytes(options.method.as_bytes())
+        .map_err(|e| format!("Invalid method: {}", e))?;
+
+    let mut client_builder = reqwest::Client::builder();
+    if let Some(ms) = options.connect_timeout {
+        client_builder = client_builder.connect_timeout(Duration::from_millis(ms));
+    }
+    if let Some(ms) = options.read_timeout {
+        client_builder = client_builder.timeout(Duration::from_millis(ms));
+    }
+    client_builder = client_builder.redirect(if options.follow_redirects {
+        // `Policy::limited` only bounds the hop count, it doesn't re-check
+        // scope per hop — a redirect to a loopback/metadata host would still
+        // be followed and its body handed back to the webview. Re-run the
+        // same scope check on every hop instead.
+        let max_redirections = options.max_redirections;
+        reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= max_redirections {
+                return attempt.error("too many redirects");
+            }
+            if is_in_scope(attempt.url()) {
+                attempt.follow()
+            } else {
+                attempt.error(format!("redirect target not allowed by scope: {}", attempt.url()))
+            }
+        })
+    } else {
+        reqwest::redirect::Policy::none()
+    });
+
+    let client = client_builder
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    let mut request = client.request(method, &options.url);
+    for (key, value) in &options.headers {
+        request = request.header(key, value);
+    }
+    if let Some(body) = options.body {
+        request = request.body(body);
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    let status = response.status().as_u16();
+    // Collected as a `Vec` rather than a map so repeated header names (e.g.
+    // multiple `Set-Cookie`s from a token exchange) aren't collapsed to the
+    // last one seen.
+    let headers: Vec<(String, String)> = response
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|v| (name.as_str().to_string(), v.to_string()))
+        })
+        .collect();
+
+    let body = match options.response_type {
+        ResponseType::Json => {
+            let bytes = response
+                .bytes()
+                .await
+                .map_err(|e| format!("Failed to read response body: {}", e))?;
+            let json = serde_json::from_slice(&bytes)
+                .map_err(|e| format!("Failed to parse response as JSON: {}", e))?;
+            ResponseBody::Json(json)
+        }
+        ResponseType::Text => {
+            let text = response
+                .text()
+                .await
+                .map_err(|e| format!("Failed to read response body: {}", e))?;
+            ResponseBody::Text(text)
+        }
+        ResponseType::Binary => {
+            let bytes = response
+                .bytes()
+                .await
+                .map_err(|e| format!("Failed to read response body: {}", e))?;
+            ResponseBody::Binary(bytes.to_vec())
+        }
+    };
+
+    Ok(HttpResponse {
+        status,
+        headers,
+        body,
+    })
+}
@@ -0,0 +1,341 @@
+use std::collections::HashMap;
+use std::io::{ErrorKind, Read, Write};
+use std::net::TcpListener;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, Manager};
+use tauri_plugin_deep_link::DeepLinkExt;
+
+// Candidate ports for the OAuth callback in dev mode, tried in order until
+// one is free. All of these must be pre-registered in WorkOS as valid
+// redirect URIs, since loopback OAuth clients can't know ahead of time which
+// one will be available at runtime.
+const VALID_PORTS: &[u16] = &[17927, 17928, 17929];
+
+/// How long a single read from a callback connection may block before the
+/// request is given up on. This bounds each read, not the request as a whole.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to keep the listener open waiting for the browser to redirect
+/// back before giving up on the whole login attempt.
+const ACCEPT_DEADLINE: Duration = Duration::from_secs(5 * 60);
+
+/// Poll interval used while waiting for a connection on the non-blocking
+/// listener.
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Payload emitted to the frontend once the callback has been parsed and
+/// the CSRF state has been validated.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct OAuthCallback {
+    code: String,
+    state: String,
+}
+
+/// Result of validating a callback's query params against the expected CSRF
+/// state, before anything is emitted. Split out from [`handle_callback_query`]
+/// so the validation logic can be unit tested without a live `AppHandle`.
+#[derive(Debug, PartialEq)]
+enum CallbackOutcome {
+    Callback(OAuthCallback),
+    Error(String),
+}
+
+/// Parse the `GET /callback?...` request line of a raw HTTP request into its
+/// query parameters. The path is joined onto a dummy base so that a full URL
+/// parser will accept it, since the request line itself only carries a
+/// path-and-query.
+fn parse_callback_query(request: &str) -> Option<HashMap<String, String>> {
+    let request_line = request.lines().next()?;
+    let mut parts = request_line.split_whitespace();
+    let _method = parts.next()?;
+    let path_and_query = parts.next()?;
+
+    let url = url::Url::parse(&format!("http://unused_base{}", path_and_query)).ok()?;
+    Some(url.query_pairs().into_owned().collect())
+}
+
+/// Validate `code`/`state`/`error` query params against the CSRF nonce the
+/// flow was started with.
+fn resolve_callback(query: &HashMap<String, String>, expected_state: &str) -> CallbackOutcome {
+    if let Some(error) = query.get("error") {
+        return CallbackOutcome::Error(error.clone());
+    }
+
+    let code = query.get("code").cloned();
+    let got_state = query.get("state").cloned();
+
+    match (code, got_state) {
+        (Some(code), Some(got_state)) if got_state == expected_state => {
+            CallbackOutcome::Callback(OAuthCallback {
+                code,
+                state: got_state,
+            })
+        }
+        (Some(_), Some(_)) => CallbackOutcome::Error("state mismatch".to_string()),
+        _ => CallbackOutcome::Error("missing code or state".to_string()),
+    }
+}
+
+/// Validate the callback and emit the matching event. Shared by the loopback
+/// server and the deep-link callback, since both end up with the same set of
+/// query parameters, just extracted from different transports.
+fn handle_callback_query(app: &AppHandle, query: &HashMap<String, String>, expected_state: &str) {
+    match resolve_callback(query, expected_state) {
+        CallbackOutcome::Callback(callback) => {
+            let _ = app.emit("oauth-callback", callback);
+        }
+        CallbackOutcome::Error(error) => {
+            let _ = app.emit("oauth-error", error);
+        }
+    }
+}
+
+/// Read from `stream` until the request headers are fully buffered (i.e. a
+/// blank line has been seen) or the read timeout elapses. A single 4096-byte
+/// read isn't enough once query strings and cookies are in play, so this
+/// keeps reading and growing the buffer instead.
+fn read_full_request(stream: &mut std::net::TcpStream) -> std::io::Result<String> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..n]);
+        if buffer.windows(4).any(|w| w == b"\r\n\r\n") {
+            break;
+        }
+    }
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+/// Bind the first free port out of [`VALID_PORTS`], returning the listener
+/// together with the port it actually bound to.
+fn bind_first_available_port() -> Result<(TcpListener, u16), String> {
+    for port in VALID_PORTS {
+        match TcpListener::bind(format!("127.0.0.1:{}", port)) {
+            Ok(listener) => return Ok((listener, *port)),
+            Err(_) => continue,
+        }
+    }
+    Err(format!(
+        "Failed to bind any of the candidate ports: {:?}",
+        VALID_PORTS
+    ))
+}
+
+#[command]
+pub fn start_oauth_server(app: AppHandle, state: String) -> Result<u16, String> {
+    // Bind whichever pre-registered port is free; another Hazel instance or
+    // unrelated process may be holding one of the others.
+    let (listener, port) = bind_first_available_port()?;
+
+    // Poll non-blockingly so the accept loop can enforce an overall deadline
+    // instead of hanging forever if the browser tab is abandoned.
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Failed to set non-blocking mode: {}", e))?;
+
+    let app_handle = app.clone();
+
+    // Spawn thread to handle the single OAuth redirect request. Unlike the
+    // previous two-phase JS hack, the whole callback URL is available on the
+    // request line itself, so there's no need for a second round-trip.
+    thread::spawn(move || {
+        let deadline = Instant::now() + ACCEPT_DEADLINE;
+
+        let mut stream = loop {
+            match listener.accept() {
+                Ok((stream, _)) => break Some(stream),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    if Instant::now() >= deadline {
+                        break None;
+                    }
+                    thread::sleep(ACCEPT_POLL_INTERVAL);
+                }
+                Err(_) => break None,
+            }
+        };
+
+        let Some(stream) = stream.as_mut() else {
+            let _ = app_handle.emit("oauth-timeout", ());
+            return;
+        };
+
+        if let Ok(request) = read_full_request(stream) {
+            match parse_callback_query(&request) {
+                Some(query) => handle_callback_query(&app_handle, &query, &state),
+                None => {
+                    let _ = app_handle
+                        .emit("oauth-error", "could not parse callback request".to_string());
+                }
+            }
+
+            let html = r#"<!DOCTYPE html>
+<html>
+<head>
+    <title>Authentication Successful</title>
+    <style>
+        body {
+            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, sans-serif;
+            display: flex;
+            align-items: center;
+            justify-content: center;
+            height: 100vh;
+            margin: 0;
+            background: #f5f5f5;
+        }
+        .container {
+            text-align: center;
+            padding: 2rem;
+        }
+        h1 { color: #333; margin-bottom: 0.5rem; }
+        p { color: #666; }
+    </style>
+</head>
+<body>
+    <div class="container">
+        <h1>Authentication Successful</h1>
+        <p>You can close this tab and return to Hazel.</p>
+    </div>
+</body>
+</html>"#;
+
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\n\r\n{}",
+                html.len(),
+                html
+            );
+            let _ = stream.write_all(response.as_bytes());
+        }
+    });
+
+    Ok(port)
+}
+
+/// Holds the CSRF nonce of whichever login attempt is currently in flight
+/// over the deep-link flow. The `on_open_url` listener is registered exactly
+/// once (see [`init_deeplink_listener`]) and reads the current value out of
+/// this cell on every callback, instead of each login attempt stacking its
+/// own listener bound to its own `state`.
+#[derive(Default)]
+pub struct DeeplinkState(Mutex<Option<String>>);
+
+/// Register the `hazel://oauth/callback` deep link listener. Call this once
+/// from `setup()`; login attempts update the expected state via
+/// [`register_oauth_deeplink`] instead of registering a new listener each
+/// time, since `on_open_url` has no way to unregister a previous closure.
+pub fn init_deeplink_listener(app: &AppHandle) -> Result<(), String> {
+    let app_handle = app.clone();
+    app.deep_link()
+        .on_open_url(move |event| {
+            for url in event.urls() {
+                if url.scheme() != "hazel"
+                    || url.host_str() != Some("oauth")
+                    || url.path() != "/callback"
+                {
+                    continue;
+                }
+                let query: HashMap<String, String> = url.query_pairs().into_owned().collect();
+
+                let expected_state = app_handle
+                    .state::<DeeplinkState>()
+                    .0
+                    .lock()
+                    .ok()
+                    .and_then(|guard| guard.clone());
+
+                if let Some(expected_state) = expected_state {
+                    handle_callback_query(&app_handle, &query, &expected_state);
+                }
+            }
+        })
+        .map_err(|e| format!("Failed to register deep link handler: {}", e))
+}
+
+/// Start a deep-link login attempt: record the CSRF nonce that the shared
+/// listener registered in [`init_deeplink_listener`] should check incoming
+/// callbacks against. Packaged builds can use this to avoid binding a port
+/// at all on platforms where that's restricted; dev builds can keep using
+/// [`start_oauth_server`] since a custom scheme usually isn't registered
+/// until the app is installed.
+#[command]
+pub fn register_oauth_deeplink(app: AppHandle, state: String) -> Result<(), String> {
+    *app.state::<DeeplinkState>()
+        .0
+        .lock()
+        .map_err(|e| e.to_string())? = Some(state);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn matching_state_resolves_to_callback() {
+        let query = query(&[("code", "abc123"), ("state", "nonce-1")]);
+
+        assert_eq!(
+            resolve_callback(&query, "nonce-1"),
+            CallbackOutcome::Callback(OAuthCallback {
+                code: "abc123".to_string(),
+                state: "nonce-1".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn mismatched_state_is_rejected() {
+        let query = query(&[("code", "abc123"), ("state", "attacker-nonce")]);
+
+        assert_eq!(
+            resolve_callback(&query, "nonce-1"),
+            CallbackOutcome::Error("state mismatch".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_code_is_rejected() {
+        let query = query(&[("state", "nonce-1")]);
+
+        assert_eq!(
+            resolve_callback(&query, "nonce-1"),
+            CallbackOutcome::Error("missing code or state".to_string())
+        );
+    }
+
+    #[test]
+    fn upstream_error_param_is_surfaced() {
+        let query = query(&[("error", "access_denied")]);
+
+        assert_eq!(
+            resolve_callback(&query, "nonce-1"),
+            CallbackOutcome::Error("access_denied".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_callback_query_extracts_code_and_state() {
+        let request = "GET /callback?code=abc123&state=nonce-1 HTTP/1.1\r\nHost: localhost\r\n\r\n";
+
+        let query = parse_callback_query(request).expect("request line should parse");
+
+        assert_eq!(query.get("code"), Some(&"abc123".to_string()));
+        assert_eq!(query.get("state"), Some(&"nonce-1".to_string()));
+    }
+}
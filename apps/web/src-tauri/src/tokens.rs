@@ -0,0 +1,127 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::{command, AppHandle, Emitter, Manager};
+
+/// If the access token is valid for less than this long, we proactively tell
+/// the frontend to refresh rather than waiting for it to expire mid-session.
+const EXPIRY_WARNING_THRESHOLD_SECS: i64 = 2 * 24 * 60 * 60;
+
+const TOKEN_FILE_NAME: &str = "tokens.json";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Tokens {
+    access_token: String,
+    refresh_token: String,
+    /// Unix timestamp (seconds) the access token expires at.
+    expires_at: i64,
+}
+
+fn token_file_path(app: &AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))?;
+    Ok(dir.join(TOKEN_FILE_NAME))
+}
+
+/// Create (or truncate) the token file already restricted to owner
+/// read/write, and write `contents` to it. The file is opened with mode
+/// `0o600` from the moment it's created, rather than written first and
+/// chmod'd after, so the token bytes are never briefly exposed under the
+/// umask-controlled default (typically group/world-readable) permissions.
+#[cfg(unix)]
+fn write_restricted(path: &std::path::Path, contents: &str) -> Result<(), String> {
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .map_err(|e| format!("Failed to open token file: {}", e))?;
+    file.write_all(contents.as_bytes())
+        .map_err(|e| format!("Failed to write token file: {}", e))
+}
+
+#[cfg(not(unix))]
+fn write_restricted(path: &std::path::Path, contents: &str) -> Result<(), String> {
+    fs::write(path, contents).map_err(|e| format!("Failed to write token file: {}", e))
+}
+
+fn now() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+/// Write tokens to a per-user file under the app data dir, like the Firezone
+/// client keeps its on-disk state under the app identifier dir.
+#[command]
+pub fn store_tokens(app: AppHandle, tokens: Tokens) -> Result<(), String> {
+    let path = token_file_path(&app)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create app data dir: {}", e))?;
+    }
+    let json = serde_json::to_string(&tokens)
+        .map_err(|e| format!("Failed to serialize tokens: {}", e))?;
+    write_restricted(&path, &json)?;
+
+    if tokens.expires_at - now() < EXPIRY_WARNING_THRESHOLD_SECS {
+        let _ = app.emit("token-expiring-soon", ());
+    }
+
+    Ok(())
+}
+
+/// Load tokens from disk, if any were ever stored. Emits `token-expiring-soon`
+/// if the access token is close to (or past) expiry.
+#[command]
+pub fn load_tokens(app: AppHandle) -> Result<Option<Tokens>, String> {
+    let path = token_file_path(&app)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let json = fs::read_to_string(&path).map_err(|e| format!("Failed to read token file: {}", e))?;
+    let tokens: Tokens =
+        serde_json::from_str(&json).map_err(|e| format!("Failed to parse token file: {}", e))?;
+
+    if tokens.expires_at - now() < EXPIRY_WARNING_THRESHOLD_SECS {
+        let _ = app.emit("token-expiring-soon", ());
+    }
+
+    Ok(Some(tokens))
+}
+
+#[command]
+pub fn clear_tokens(app: AppHandle) -> Result<(), String> {
+    let path = token_file_path(&app)?;
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("Failed to remove token file: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Delete the token file and the webview cache dir, for clean first-run
+/// testing of the login flow.
+#[command]
+pub fn reset_state(app: AppHandle) -> Result<(), String> {
+    clear_tokens(app.clone())?;
+
+    let cache_dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| format!("Failed to resolve app cache dir: {}", e))?;
+    if cache_dir.exists() {
+        fs::remove_dir_all(&cache_dir)
+            .map_err(|e| format!("Failed to remove webview cache dir: {}", e))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,299 @@
+//! WebSocket transport for realtime chat. `wss://` is handled by wrapping the
+//! TCP stream in a `native_tls` connector ourselves (see [`Conn`]) rather than
+//! relying on tungstenite's bundled `connect()` helper, so that we keep a
+//! concrete stream type we can reach into to adjust read timeouts.
+
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{command, AppHandle, Emitter, State};
+use tungstenite::client::IntoClientRequest;
+use tungstenite::{Message, WebSocket};
+
+/// Base delay for reconnect backoff; doubled on each consecutive failure up
+/// to [`MAX_BACKOFF`].
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// How often to send a ping while the connection is idle.
+const PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long a single `read()` call is allowed to block once connected. Kept
+/// short (rather than `PING_INTERVAL`) so a queued `ws_send` is picked up
+/// promptly instead of waiting out a long idle read.
+const READ_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+enum Command {
+    Send(Message),
+    Close,
+}
+
+/// Either side of a plain or TLS-wrapped TCP connection. Kept as our own
+/// concrete enum (rather than going through tungstenite's `connect()`, which
+/// returns a `MaybeTlsStream` with no read-timeout accessor) so we can reach
+/// the inner `TcpStream` to adjust its read timeout after the handshake.
+enum Conn {
+    Plain(TcpStream),
+    Tls(native_tls::TlsStream<TcpStream>),
+}
+
+impl Conn {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        match self {
+            Conn::Plain(s) => s.set_read_timeout(timeout),
+            Conn::Tls(s) => s.get_ref().set_read_timeout(timeout),
+        }
+    }
+}
+
+impl Read for Conn {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Conn::Plain(s) => s.read(buf),
+            Conn::Tls(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for Conn {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Conn::Plain(s) => s.write(buf),
+            Conn::Tls(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Conn::Plain(s) => s.flush(),
+            Conn::Tls(s) => s.flush(),
+        }
+    }
+}
+
+/// A running connection's command sender plus the handle of the thread
+/// driving it, so a later `ws_connect` can cleanly shut the old one down
+/// instead of leaking it.
+struct Connection {
+    commands: Sender<Command>,
+    handle: JoinHandle<()>,
+}
+
+/// Holds the currently running connection, if any.
+#[derive(Default)]
+pub struct WsState(Mutex<Option<Connection>>);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase", tag = "kind")]
+enum WsMessagePayload {
+    Text { data: String },
+    Binary { data: Vec<u8> },
+}
+
+/// Connect to `url`, performing the WebSocket handshake over a stream we
+/// built ourselves so we can tune its read timeout afterwards. Returns a
+/// `String` error instead of panicking, since a malformed URL or auth token
+/// is caller-controlled input, not a programming error.
+fn connect_to(url: &str, auth_token: Option<&str>) -> Result<WebSocket<Conn>, String> {
+    let parsed = url::Url::parse(url).map_err(|e| format!("Invalid websocket URL: {}", e))?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| "websocket URL has no host".to_string())?
+        .to_string();
+    let port = parsed
+        .port_or_known_default()
+        .ok_or_else(|| "could not determine websocket port".to_string())?;
+    let use_tls = match parsed.scheme() {
+        "wss" => true,
+        "ws" => false,
+        other => return Err(format!("unsupported websocket scheme: {}", other)),
+    };
+
+    let tcp = TcpStream::connect((host.as_str(), port))
+        .map_err(|e| format!("TCP connect to {}:{} failed: {}", host, port, e))?;
+
+    let conn = if use_tls {
+        let connector = native_tls::TlsConnector::new()
+            .map_err(|e| format!("Failed to build TLS connector: {}", e))?;
+        let tls = connector
+            .connect(&host, tcp)
+            .map_err(|e| format!("TLS handshake with {} failed: {}", host, e))?;
+        Conn::Tls(tls)
+    } else {
+        Conn::Plain(tcp)
+    };
+
+    let mut request = url
+        .into_client_request()
+        .map_err(|e| format!("Invalid websocket request: {}", e))?;
+    if let Some(token) = auth_token {
+        let value = format!("Bearer {}", token)
+            .parse()
+            .map_err(|e| format!("Invalid auth token header value: {}", e))?;
+        request.headers_mut().insert("Authorization", value);
+    }
+
+    let (socket, _response) = tungstenite::client(request, conn)
+        .map_err(|e| format!("WebSocket handshake failed: {}", e))?;
+
+    socket
+        .get_ref()
+        .set_read_timeout(Some(READ_POLL_INTERVAL))
+        .map_err(|e| format!("Failed to set read timeout: {}", e))?;
+
+    Ok(socket)
+}
+
+/// Drive a single connection attempt to completion: read/write until the
+/// socket closes, errors out, or a `Command::Close` arrives. Returns `true`
+/// if the caller asked us to close (so the outer reconnect loop should stop),
+/// `false` if we should reconnect.
+fn run_connected(app: &AppHandle, mut socket: WebSocket<Conn>, command_rx: &Receiver<Command>) -> bool {
+    let mut last_ping = Instant::now();
+
+    loop {
+        match command_rx.try_recv() {
+            Ok(Command::Send(msg)) => {
+                if socket.send(msg).is_err() {
+                    let _ = app.emit("ws-closed", ());
+                    return false;
+                }
+                continue;
+            }
+            Ok(Command::Close) => {
+                let _ = socket.close(None);
+                let _ = app.emit("ws-closed", ());
+                return true;
+            }
+            Err(std::sync::mpsc::TryRecvError::Empty) => {}
+            Err(std::sync::mpsc::TryRecvError::Disconnected) => {
+                let _ = socket.close(None);
+                let _ = app.emit("ws-closed", ());
+                return true;
+            }
+        }
+
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let _ = app.emit(
+                    "ws-message",
+                    WsMessagePayload::Text { data: text.to_string() },
+                );
+            }
+            Ok(Message::Binary(data)) => {
+                let _ = app.emit(
+                    "ws-message",
+                    WsMessagePayload::Binary { data: data.to_vec() },
+                );
+            }
+            Ok(Message::Ping(data)) => {
+                let _ = socket.send(Message::Pong(data));
+            }
+            Ok(Message::Close(_)) => {
+                let _ = app.emit("ws-closed", ());
+                return false;
+            }
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if e.kind() == io::ErrorKind::WouldBlock || e.kind() == io::ErrorKind::TimedOut =>
+            {
+                if last_ping.elapsed() >= PING_INTERVAL {
+                    let _ = socket.send(Message::Ping(Vec::new().into()));
+                    last_ping = Instant::now();
+                }
+            }
+            Err(e) => {
+                let _ = app.emit("ws-error", e.to_string());
+                let _ = app.emit("ws-closed", ());
+                return false;
+            }
+        }
+    }
+}
+
+fn run_connection(app: AppHandle, url: String, auth_token: Option<String>, command_rx: Receiver<Command>) {
+    let mut backoff = INITIAL_BACKOFF;
+
+    loop {
+        match connect_to(&url, auth_token.as_deref()) {
+            Ok(socket) => {
+                backoff = INITIAL_BACKOFF;
+                if run_connected(&app, socket, &command_rx) {
+                    return;
+                }
+            }
+            Err(e) => {
+                let _ = app.emit("ws-error", e);
+            }
+        }
+
+        // Give the caller a chance to close us out between reconnect attempts.
+        if let Ok(Command::Close) = command_rx.recv_timeout(backoff) {
+            let _ = app.emit("ws-closed", ());
+            return;
+        }
+        backoff = std::cmp::min(backoff * 2, MAX_BACKOFF);
+    }
+}
+
+/// Ask a previously running connection to close and wait for its thread to
+/// exit, so `ws_connect` never leaves a stale connection behind still
+/// emitting events after a reconnect.
+fn shut_down(connection: Connection) {
+    let _ = connection.commands.send(Command::Close);
+    let _ = connection.handle.join();
+}
+
+#[command]
+pub fn ws_connect(
+    app: AppHandle,
+    state: State<WsState>,
+    url: String,
+    auth_token: Option<String>,
+) -> Result<(), String> {
+    let previous = state.0.lock().map_err(|e| e.to_string())?.take();
+    if let Some(previous) = previous {
+        shut_down(previous);
+    }
+
+    let (tx, rx) = channel();
+    let handle = thread::spawn(move || run_connection(app, url, auth_token, rx));
+
+    *state.0.lock().map_err(|e| e.to_string())? = Some(Connection {
+        commands: tx,
+        handle,
+    });
+
+    Ok(())
+}
+
+#[command]
+pub fn ws_send(state: State<WsState>, text: Option<String>, binary: Option<Vec<u8>>) -> Result<(), String> {
+    let guard = state.0.lock().map_err(|e| e.to_string())?;
+    let connection = guard.as_ref().ok_or("no active websocket connection")?;
+
+    let message = match (text, binary) {
+        (Some(text), _) => Message::Text(text.into()),
+        (None, Some(data)) => Message::Binary(data.into()),
+        (None, None) => return Err("either text or binary must be provided".to_string()),
+    };
+
+    connection
+        .commands
+        .send(Command::Send(message))
+        .map_err(|e| e.to_string())
+}
+
+#[command]
+pub fn ws_close(state: State<WsState>) -> Result<(), String> {
+    let previous = state.0.lock().map_err(|e| e.to_string())?.take();
+    if let Some(previous) = previous {
+        shut_down(previous);
+    }
+    Ok(())
+}